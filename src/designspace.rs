@@ -0,0 +1,297 @@
+//! Parsing of a subset of the designspace XML format (axes, their `<map>`
+//! entries, source locations, and instances) into the same [`AxisInfo`] /
+//! [`InstanceInfo`] / [`MasterLocation`] shapes the JS caller would otherwise
+//! hand-build.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{AxisCoordinate, AxisInfo, AxisMapEntry, InstanceInfo, MasterLocation, Message, Span};
+
+pub(crate) struct ParsedDesignspace {
+    pub axes: Vec<AxisInfo>,
+    pub instances: Vec<InstanceInfo>,
+    pub masters: Vec<MasterLocation>,
+}
+
+pub(crate) fn parse(xml: &str) -> Result<ParsedDesignspace, Message> {
+    let doc: DesignspaceDocument =
+        quick_xml::de::from_str(xml).map_err(|e| designspace_diagnostic(&e.to_string()))?;
+
+    let axis_name_to_tag: HashMap<&str, &str> = doc
+        .axes
+        .axis
+        .iter()
+        .map(|a| (a.name.as_str(), a.tag.as_str()))
+        .collect();
+
+    for source in &doc.sources.source {
+        for dimension in source.location.iter().flat_map(|l| l.dimension.iter()) {
+            let Some(axis) = axis_name_to_tag
+                .get(dimension.name.as_str())
+                .and_then(|tag| doc.axes.axis.iter().find(|a| a.tag == *tag))
+            else {
+                continue;
+            };
+            if dimension.xvalue < axis.minimum || dimension.xvalue > axis.maximum {
+                return Err(designspace_diagnostic(&format!(
+                    "source '{}' locates axis '{}' at {}, outside its [{}, {}] range",
+                    source.name, dimension.name, dimension.xvalue, axis.minimum, axis.maximum
+                )));
+            }
+        }
+    }
+
+    let axes = doc
+        .axes
+        .axis
+        .iter()
+        .map(|a| {
+            let mapping = (!a.map.is_empty())
+                .then(|| a.map.iter().map(|m| AxisMapEntry::new(m.input, m.output)).collect());
+            AxisInfo::new(a.tag.clone(), a.minimum, a.default, a.maximum, mapping)
+        })
+        .collect();
+
+    // `<location>`/`<dimension>` `xvalue`s are design-space coordinates (the
+    // axis's post-`<map>` space), not user-space ones; `AxisCoordinate` just
+    // carries them through as-is, and it's `normalize_master_location` in
+    // lib.rs that converts them back to user space via the axis's mapping.
+    let masters = doc
+        .sources
+        .source
+        .iter()
+        .map(|source| {
+            let coordinates = source
+                .location
+                .iter()
+                .flat_map(|l| l.dimension.iter())
+                .filter_map(|d| {
+                    axis_name_to_tag
+                        .get(d.name.as_str())
+                        .map(|tag| AxisCoordinate::new(tag.to_string(), d.xvalue))
+                })
+                .collect();
+            MasterLocation::new(coordinates)
+        })
+        .collect();
+
+    let instances = doc
+        .instances
+        .instance
+        .iter()
+        .map(|i| {
+            let coordinates = i
+                .location
+                .iter()
+                .flat_map(|l| l.dimension.iter())
+                .filter_map(|d| {
+                    // Instance `xvalue`s are design-space too, but `InstanceInfo`
+                    // coordinates are user-space (they go straight into `fvar`),
+                    // so invert them through the axis's `<map>` here.
+                    let axis = doc.axes.axis.iter().find(|a| a.name == d.name)?;
+                    let mapping: Vec<(f64, f64)> =
+                        axis.map.iter().map(|m| (m.input, m.output)).collect();
+                    let anchored = if mapping.is_empty() {
+                        Vec::new()
+                    } else {
+                        crate::anchor_mapping(axis.minimum, axis.default, axis.maximum, &mapping)
+                    };
+                    let user_value = crate::design_to_user(&anchored, d.xvalue);
+                    Some(AxisCoordinate::new(axis.tag.clone(), user_value))
+                })
+                .collect();
+            InstanceInfo::new(
+                i.stylename.clone().unwrap_or_else(|| i.name.clone()),
+                i.postscriptfontname.clone(),
+                coordinates,
+                None,
+            )
+        })
+        .collect();
+
+    Ok(ParsedDesignspace {
+        axes,
+        instances,
+        masters,
+    })
+}
+
+fn designspace_diagnostic(text: &str) -> Message {
+    Message {
+        level: "error".to_string(),
+        text: format!("designspace: {text}"),
+        span: Span { start: 0, end: 0 },
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "designspace")]
+struct DesignspaceDocument {
+    #[serde(rename = "axes", default)]
+    axes: AxesElement,
+    #[serde(rename = "sources", default)]
+    sources: SourcesElement,
+    #[serde(rename = "instances", default)]
+    instances: InstancesElement,
+}
+
+#[derive(Deserialize, Default)]
+struct AxesElement {
+    #[serde(rename = "axis", default)]
+    axis: Vec<AxisElement>,
+}
+
+#[derive(Deserialize)]
+struct AxisElement {
+    #[serde(rename = "@tag")]
+    tag: String,
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@minimum")]
+    minimum: f64,
+    #[serde(rename = "@default")]
+    default: f64,
+    #[serde(rename = "@maximum")]
+    maximum: f64,
+    #[serde(rename = "map", default)]
+    map: Vec<MapElement>,
+}
+
+#[derive(Deserialize)]
+struct MapElement {
+    #[serde(rename = "@input")]
+    input: f64,
+    #[serde(rename = "@output")]
+    output: f64,
+}
+
+#[derive(Deserialize, Default)]
+struct SourcesElement {
+    #[serde(rename = "source", default)]
+    source: Vec<SourceElement>,
+}
+
+#[derive(Deserialize)]
+struct SourceElement {
+    #[serde(rename = "@name", default)]
+    name: String,
+    #[serde(default)]
+    location: Option<LocationElement>,
+}
+
+#[derive(Deserialize, Default)]
+struct InstancesElement {
+    #[serde(rename = "instance", default)]
+    instance: Vec<InstanceElement>,
+}
+
+#[derive(Deserialize)]
+struct InstanceElement {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@stylename", default)]
+    stylename: Option<String>,
+    #[serde(rename = "@postscriptfontname", default)]
+    postscriptfontname: Option<String>,
+    #[serde(default)]
+    location: Option<LocationElement>,
+}
+
+#[derive(Deserialize, Default)]
+struct LocationElement {
+    #[serde(rename = "dimension", default)]
+    dimension: Vec<DimensionElement>,
+}
+
+#[derive(Deserialize)]
+struct DimensionElement {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@xvalue")]
+    xvalue: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<designspace format="4.0">
+    <axes>
+        <axis tag="wght" name="Weight" minimum="100" default="400" maximum="900">
+            <map input="100" output="100"/>
+            <map input="400" output="300"/>
+            <map input="900" output="900"/>
+        </axis>
+    </axes>
+    <sources>
+        <source name="Light">
+            <location>
+                <dimension name="Weight" xvalue="100"/>
+            </location>
+        </source>
+        <source name="Regular">
+            <location>
+                <dimension name="Weight" xvalue="400"/>
+            </location>
+        </source>
+    </sources>
+    <instances>
+        <instance name="Weight Bold" stylename="Bold" postscriptfontname="MyFont-Bold">
+            <location>
+                <dimension name="Weight" xvalue="700"/>
+            </location>
+        </instance>
+    </instances>
+</designspace>"#;
+
+    #[test]
+    fn parses_axes_sources_and_instances() {
+        let parsed = parse(DOC).unwrap();
+
+        assert_eq!(parsed.axes.len(), 1);
+        assert_eq!(parsed.axes[0].axis_tag, "wght");
+        assert_eq!(parsed.axes[0].min_value, 100.0);
+        assert_eq!(parsed.axes[0].default_value, 400.0);
+        assert_eq!(parsed.axes[0].max_value, 900.0);
+        let mapping = parsed.axes[0].mapping.as_ref().unwrap();
+        assert_eq!(mapping.len(), 3);
+        assert_eq!((mapping[1].user_value, mapping[1].mapped_value), (400.0, 300.0));
+
+        assert_eq!(parsed.masters.len(), 2);
+        assert_eq!(parsed.masters[0].coordinates[0].axis_tag, "wght");
+        assert_eq!(parsed.masters[0].coordinates[0].value, 100.0);
+
+        assert_eq!(parsed.instances.len(), 1);
+        assert_eq!(parsed.instances[0].name, "Bold");
+        assert_eq!(
+            parsed.instances[0].postscript_name.as_deref(),
+            Some("MyFont-Bold")
+        );
+        // Design-space 700 lies between the (400 -> 300) and (900 -> 900)
+        // map entries, which puts it at user-space 733.33.
+        assert!((parsed.instances[0].coordinates[0].value - 733.333_333_3).abs() < 0.001);
+    }
+
+    #[test]
+    fn rejects_a_source_located_outside_its_axis_range() {
+        let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<designspace format="4.0">
+    <axes>
+        <axis tag="wght" name="Weight" minimum="100" default="400" maximum="900"/>
+    </axes>
+    <sources>
+        <source name="TooLight">
+            <location>
+                <dimension name="Weight" xvalue="50"/>
+            </location>
+        </source>
+    </sources>
+</designspace>"#;
+
+        let err = parse(doc).unwrap_err();
+        assert!(err.text.contains("outside"));
+    }
+}
@@ -1,6 +1,9 @@
 use wasm_bindgen::prelude::*;
 
+mod designspace;
+
 use std::{
+    cmp::Ordering,
     collections::{BTreeSet, HashMap},
     fmt::Display,
     path::Path,
@@ -13,17 +16,21 @@ use fea_rs::{
     DiagnosticSet, GlyphMap,
 };
 use fontdrasil::{
-    coords::{NormalizedLocation, UserCoord},
+    coords::{CoordConverter, DesignCoord, NormalizedCoord, NormalizedLocation, UserCoord},
     types::{Axes, Axis},
     variations::VariationModel,
 };
 use write_fonts::{
     tables::{
-        fvar::{AxisInstanceArrays, Fvar, VariationAxisRecord},
+        avar::{Avar, AxisValueMap, SegmentMaps},
+        fvar::{AxisInstanceArrays, Fvar, InstanceRecord, VariationAxisRecord},
+        gsub::Gsub,
+        layout::FeatureVariationRecord,
         name::NameRecord,
+        stat::{AxisRecord, AxisValue, AxisValueFormat1, AxisValueFormat2, Stat},
         variations::VariationRegion,
     },
-    types::{NameId, Tag},
+    types::{Fixed, NameId, Tag},
     OtRound,
 };
 
@@ -45,6 +52,29 @@ pub struct InsertMarker {
     pub lookup_id: usize,
 }
 
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Copy)]
+pub struct AxisMapEntry {
+    #[wasm_bindgen(js_name = "userValue")]
+    pub user_value: f64,
+    #[wasm_bindgen(js_name = "mappedValue")]
+    pub mapped_value: f64,
+}
+
+#[wasm_bindgen]
+impl AxisMapEntry {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        #[wasm_bindgen(js_name = "userValue")] user_value: f64,
+        #[wasm_bindgen(js_name = "mappedValue")] mapped_value: f64,
+    ) -> Self {
+        AxisMapEntry {
+            user_value,
+            mapped_value,
+        }
+    }
+}
+
 #[wasm_bindgen(getter_with_clone)]
 pub struct AxisInfo {
     #[wasm_bindgen(js_name = "axisTag")]
@@ -55,6 +85,10 @@ pub struct AxisInfo {
     pub default_value: f64,
     #[wasm_bindgen(js_name = "maxValue")]
     pub max_value: f64,
+    // Designspace-style `<map>` entries, ordered by user value. When absent
+    // or empty the axis normalizes linearly and its `avar` segment map is
+    // left empty (`avar` is only emitted when some axis has a mapping).
+    pub mapping: Option<Vec<AxisMapEntry>>,
 }
 
 #[wasm_bindgen]
@@ -65,54 +99,357 @@ impl AxisInfo {
         #[wasm_bindgen(js_name = "minValue")] min_value: f64,
         #[wasm_bindgen(js_name = "defaultValue")] default_value: f64,
         #[wasm_bindgen(js_name = "maxValue")] max_value: f64,
+        mapping: Option<Vec<AxisMapEntry>>,
     ) -> Self {
         AxisInfo {
             axis_tag,
             min_value,
             default_value,
             max_value,
+            mapping,
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct AxisCoordinate {
+    #[wasm_bindgen(js_name = "axisTag")]
+    pub axis_tag: String,
+    pub value: f64,
+}
+
+#[wasm_bindgen]
+impl AxisCoordinate {
+    #[wasm_bindgen(constructor)]
+    pub fn new(#[wasm_bindgen(js_name = "axisTag")] axis_tag: String, value: f64) -> Self {
+        AxisCoordinate { axis_tag, value }
+    }
+}
+
+/// One master's design-space coordinates (post-`<map>`, the same as user-space
+/// on axes without a mapping), used to give `resolve_variable_metric` the full
+/// set of locations the font is designed at (axes not mentioned default to the
+/// axis default).
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct MasterLocation {
+    pub coordinates: Vec<AxisCoordinate>,
+}
+
+#[wasm_bindgen]
+impl MasterLocation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(coordinates: Vec<AxisCoordinate>) -> Self {
+        MasterLocation { coordinates }
+    }
+}
+
+/// A STAT axis value label for one axis of an instance. `range_min_value`/
+/// `range_max_value` select a format 2 (range) `AxisValue`; when both are
+/// absent a format 1 (single value) `AxisValue` is emitted instead.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct StyleAttribute {
+    #[wasm_bindgen(js_name = "axisTag")]
+    pub axis_tag: String,
+    pub label: String,
+    #[wasm_bindgen(js_name = "rangeMinValue")]
+    pub range_min_value: Option<f64>,
+    #[wasm_bindgen(js_name = "rangeMaxValue")]
+    pub range_max_value: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl StyleAttribute {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        #[wasm_bindgen(js_name = "axisTag")] axis_tag: String,
+        label: String,
+        #[wasm_bindgen(js_name = "rangeMinValue")] range_min_value: Option<f64>,
+        #[wasm_bindgen(js_name = "rangeMaxValue")] range_max_value: Option<f64>,
+    ) -> Self {
+        StyleAttribute {
+            axis_tag,
+            label,
+            range_min_value,
+            range_max_value,
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct InstanceInfo {
+    pub name: String,
+    #[wasm_bindgen(js_name = "postscriptName")]
+    pub postscript_name: Option<String>,
+    pub coordinates: Vec<AxisCoordinate>,
+    #[wasm_bindgen(js_name = "styleAttributes")]
+    pub style_attributes: Option<Vec<StyleAttribute>>,
+}
+
+#[wasm_bindgen]
+impl InstanceInfo {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        name: String,
+        #[wasm_bindgen(js_name = "postscriptName")] postscript_name: Option<String>,
+        coordinates: Vec<AxisCoordinate>,
+        #[wasm_bindgen(js_name = "styleAttributes")] style_attributes: Option<Vec<StyleAttribute>>,
+    ) -> Self {
+        InstanceInfo {
+            name,
+            postscript_name,
+            coordinates,
+            style_attributes,
         }
     }
 }
 
 struct SimpleVariationInfo {
     axes: Axes,
+    // The full set of master locations the font is designed at. When
+    // present, every metric's deltas are computed over a model built from
+    // this set rather than from whatever subset of locations that metric
+    // alone happens to define, so all metrics share one region decomposition.
+    global_locations: BTreeSet<NormalizedLocation>,
+    global_model: Option<VariationModel>,
     model_cache: std::cell::RefCell<HashMap<BTreeSet<NormalizedLocation>, VariationModel>>,
 }
 
 impl SimpleVariationInfo {
-    fn new(axis_infos: Vec<AxisInfo>) -> Self {
-        let axes = Axes::new(
-            axis_infos
+    /// Builds the variation model along with one `avar` `SegmentMaps` per axis
+    /// (`None` for axes that normalize linearly, i.e. have no `<map>` entries).
+    fn try_new(
+        axis_infos: Vec<AxisInfo>,
+        global_masters: Option<Vec<MasterLocation>>,
+    ) -> Result<(Self, Vec<Option<SegmentMaps>>), Message> {
+        let mut axes = Vec::with_capacity(axis_infos.len());
+        let mut segment_maps = Vec::with_capacity(axis_infos.len());
+        let mut axis_ranges = Vec::with_capacity(axis_infos.len());
+
+        for a in axis_infos {
+            let tag = Tag::from_str(&a.axis_tag).unwrap();
+            let min = UserCoord::new(a.min_value);
+            let default = UserCoord::new(a.default_value);
+            let max = UserCoord::new(a.max_value);
+
+            let mut mapping: Vec<(f64, f64)> = a
+                .mapping
+                .unwrap_or_default()
                 .into_iter()
-                .map(|a| {
-                    let tag = Tag::from_str(&a.axis_tag).unwrap();
-                    let min = UserCoord::new(a.min_value);
-                    let default = UserCoord::new(a.default_value);
-                    let max = UserCoord::new(a.max_value);
-                    Axis {
-                        name: a.axis_tag,
-                        tag,
-                        min,
-                        default,
-                        max,
-                        hidden: false,
-                        converter: fontdrasil::coords::CoordConverter::default_normalization(
-                            min, default, max,
-                        ),
-                        localized_names: Default::default(),
-                    }
-                })
-                .collect(),
-        );
+                .map(|e| (e.user_value, e.mapped_value))
+                .collect();
+            if mapping
+                .iter()
+                .any(|&(user, mapped)| !user.is_finite() || !mapped.is_finite())
+            {
+                return Err(axis_diagnostic(
+                    &a.axis_tag,
+                    "avar mapping values must be finite",
+                ));
+            }
+            mapping.sort_by(|p, q| p.0.partial_cmp(&q.0).unwrap_or(Ordering::Equal));
+            for pair in mapping.windows(2) {
+                if pair[1].0 == pair[0].0 && pair[1].1 != pair[0].1 {
+                    return Err(axis_diagnostic(
+                        &a.axis_tag,
+                        "avar mapping has conflicting entries for the same input value",
+                    ));
+                }
+            }
+            mapping.dedup_by(|p, q| p.0 == q.0);
+            for pair in mapping.windows(2) {
+                if pair[1].0 <= pair[0].0 {
+                    return Err(axis_diagnostic(
+                        &a.axis_tag,
+                        "avar mapping inputs must be strictly increasing",
+                    ));
+                }
+            }
+
+            // Designspace `<source>` (master) locations give a design-space
+            // (post-`<map>`) coordinate, not a user-space one, so we keep the
+            // anchored user<->design pairs around to invert that mapping in
+            // `normalize_master_location` instead of treating it as linear.
+            let anchored_mapping = if mapping.is_empty() {
+                Vec::new()
+            } else {
+                anchor_mapping(a.min_value, a.default_value, a.max_value, &mapping)
+            };
+            axis_ranges.push((tag, a.min_value, a.default_value, a.max_value, anchored_mapping.clone()));
+
+            let converter = if anchored_mapping.is_empty() {
+                CoordConverter::default_normalization(min, default, max)
+            } else {
+                let examples: Vec<(UserCoord, DesignCoord)> = anchored_mapping
+                    .iter()
+                    .map(|&(user, mapped)| (UserCoord::new(user), DesignCoord::new(mapped)))
+                    .collect();
+                let default_idx = anchored_mapping
+                    .iter()
+                    .position(|&(user, _)| user == a.default_value)
+                    .unwrap_or(0);
+                CoordConverter::new(examples, default_idx)
+            };
 
-        Self {
-            axes,
-            model_cache: Default::default(),
+            segment_maps.push(build_segment_map(
+                a.min_value,
+                a.default_value,
+                a.max_value,
+                &mapping,
+            ));
+
+            axes.push(Axis {
+                name: a.axis_tag,
+                tag,
+                min,
+                default,
+                max,
+                hidden: false,
+                converter,
+                localized_names: Default::default(),
+            });
         }
+
+        let axes = Axes::new(axes);
+        let axis_order = axes.axis_order();
+
+        let global_locations: BTreeSet<NormalizedLocation> = global_masters
+            .unwrap_or_default()
+            .iter()
+            .map(|master| normalize_master_location(&axis_ranges, master))
+            .collect();
+        let global_model = (!global_locations.is_empty())
+            .then(|| VariationModel::new(global_locations.iter().cloned().collect(), axis_order));
+
+        Ok((
+            Self {
+                axes,
+                global_locations,
+                global_model,
+                model_cache: Default::default(),
+            },
+            segment_maps,
+        ))
     }
 }
 
+fn axis_diagnostic(axis_tag: &str, text: &str) -> Message {
+    Message {
+        level: "error".to_string(),
+        text: format!("axis '{axis_tag}': {text}"),
+        span: Span { start: 0, end: 0 },
+    }
+}
+
+/// Linearly normalizes `value` to the range `[-1, 1]` through `min`/`default`/`max`,
+/// exactly as `fvar` normalization does (ignoring any `avar` mapping).
+fn linear_normalize(min: f64, default: f64, max: f64, value: f64) -> f64 {
+    if value <= min {
+        -1.0
+    } else if value >= max {
+        1.0
+    } else if value < default {
+        -((default - value) / (default - min))
+    } else if value > default {
+        (value - default) / (max - default)
+    } else {
+        0.0
+    }
+}
+
+/// Normalizes a [`MasterLocation`]'s design-space coordinates (a designspace
+/// `<source>` location's `xvalue`s, i.e. post-`<map>`) into a
+/// [`NormalizedLocation`], one axis at a time; axes the master doesn't
+/// mention fall back to that axis's (user-space) default.
+fn normalize_master_location(
+    axis_ranges: &[(Tag, f64, f64, f64, Vec<(f64, f64)>)],
+    master: &MasterLocation,
+) -> NormalizedLocation {
+    let mut location = NormalizedLocation::new();
+    for (tag, min, default, max, anchored_mapping) in axis_ranges {
+        let user_value = match master
+            .coordinates
+            .iter()
+            .find(|c| Tag::from_str(&c.axis_tag).ok() == Some(*tag))
+        {
+            Some(coordinate) => design_to_user(anchored_mapping, coordinate.value),
+            None => *default,
+        };
+        location.insert(*tag, NormalizedCoord::new(linear_normalize(*min, *default, *max, user_value)));
+    }
+    location
+}
+
+/// Inverts an axis's anchored user->design `<map>` (as built by
+/// [`anchor_mapping`]) to recover the user-space value for a design-space
+/// coordinate, interpolating piecewise-linearly between anchors and clamping
+/// outside their range. An empty mapping means the axis has no `<map>`, so
+/// design space and user space coincide.
+fn design_to_user(anchored_mapping: &[(f64, f64)], design_value: f64) -> f64 {
+    let (Some(&(first_user, first_design)), Some(&(last_user, last_design))) =
+        (anchored_mapping.first(), anchored_mapping.last())
+    else {
+        return design_value;
+    };
+
+    if design_value <= first_design {
+        return first_user;
+    }
+    if design_value >= last_design {
+        return last_user;
+    }
+
+    for pair in anchored_mapping.windows(2) {
+        let (user0, design0) = pair[0];
+        let (user1, design1) = pair[1];
+        if design_value >= design0 && design_value <= design1 {
+            if design1 == design0 {
+                return user0;
+            }
+            let t = (design_value - design0) / (design1 - design0);
+            return user0 + t * (user1 - user0);
+        }
+    }
+
+    design_value
+}
+
+/// Adds identity anchor points at `min`/`default`/`max` when the designer's
+/// mapping doesn't already define them, matching how a designspace `<map>`
+/// implicitly pins its endpoints.
+fn anchor_mapping(min: f64, default: f64, max: f64, mapping: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut anchored = mapping.to_vec();
+    for anchor in [min, default, max] {
+        if !anchored.iter().any(|&(user, _)| user == anchor) {
+            anchored.push((anchor, anchor));
+        }
+    }
+    anchored.sort_by(|p, q| p.0.partial_cmp(&q.0).unwrap_or(Ordering::Equal));
+    anchored
+}
+
+/// Builds the `avar` segment map for a single axis, or `None` if it has no
+/// nonlinear mapping (and thus normalizes linearly with no `avar` entry).
+fn build_segment_map(min: f64, default: f64, max: f64, mapping: &[(f64, f64)]) -> Option<SegmentMaps> {
+    if mapping.is_empty() {
+        return None;
+    }
+
+    let anchored = anchor_mapping(min, default, max, mapping);
+    let mut axis_value_map: Vec<AxisValueMap> = anchored
+        .iter()
+        .map(|&(user, mapped)| AxisValueMap {
+            from_coordinate: linear_normalize(min, default, max, user).ot_round(),
+            to_coordinate: linear_normalize(min, default, max, mapped).ot_round(),
+        })
+        .collect();
+    axis_value_map.dedup_by(|a, b| a.from_coordinate == b.from_coordinate && a.to_coordinate == b.to_coordinate);
+
+    Some(SegmentMaps::new(axis_value_map))
+}
+
 #[derive(Debug)]
 pub struct VariationError;
 
@@ -155,28 +492,74 @@ impl VariationInfo for SimpleVariationInfo {
 
         let locations: BTreeSet<_> = point_seqs.keys().cloned().collect();
 
-        // Reuse or create a model for the locations we are asked for
-        let mut model_cache = self.model_cache.borrow_mut();
-        let var_model = model_cache.entry(locations.clone()).or_insert_with(|| {
-            VariationModel::new(locations.iter().cloned().collect(), self.axes.axis_order())
-        });
-
         // Only 1 value per region for our input
-        let deltas: Vec<_> = var_model
-            .deltas(&point_seqs)
-            .map_err(|_| VariationError)?
-            .into_iter()
-            .map(|(region, values)| {
-                assert!(values.len() == 1, "{} values?!", values.len());
-                (region, values[0])
+        let compute_deltas = |model: &VariationModel, seqs: &HashMap<NormalizedLocation, Vec<f64>>| {
+            model.deltas(seqs).map_err(|_| VariationError).map(|deltas| {
+                deltas
+                    .into_iter()
+                    .map(|(region, values)| {
+                        assert!(values.len() == 1, "{} values?!", values.len());
+                        (region, values[0])
+                    })
+                    .collect::<Vec<_>>()
             })
-            .collect();
+        };
+
+        let (deltas, default_location) = match &self.global_model {
+            None => {
+                // No global master set was supplied: fall back to a model
+                // scoped to just the locations this metric is asked for,
+                // reusing it across calls that share the same location set.
+                let mut model_cache = self.model_cache.borrow_mut();
+                let var_model = model_cache.entry(locations.clone()).or_insert_with(|| {
+                    VariationModel::new(locations.iter().cloned().collect(), self.axes.axis_order())
+                });
+                (compute_deltas(var_model, &point_seqs)?, var_model.default.clone())
+            }
+            Some(global_model) if locations == self.global_locations => {
+                // Fast path: this metric is already defined at every global
+                // master, so it already shares the global region decomposition.
+                (compute_deltas(global_model, &point_seqs)?, global_model.default.clone())
+            }
+            Some(global_model) => {
+                // Sparse case: interpolate the missing master values from a
+                // model scoped to the masters this metric does define, then
+                // let the global model produce deltas so every value shares
+                // the same region decomposition.
+                let mut model_cache = self.model_cache.borrow_mut();
+                let sparse_model = model_cache.entry(locations.clone()).or_insert_with(|| {
+                    VariationModel::new(locations.iter().cloned().collect(), self.axes.axis_order())
+                });
+                let sparse_deltas = compute_deltas(sparse_model, &point_seqs)?;
+
+                let filled_point_seqs: HashMap<_, _> = self
+                    .global_locations
+                    .iter()
+                    .map(|location| {
+                        let value = point_seqs.get(location).cloned().unwrap_or_else(|| {
+                            vec![sparse_deltas
+                                .iter()
+                                .map(|(region, value)| {
+                                    region.scalar_at(location).into_inner() * value
+                                })
+                                .sum()]
+                        });
+                        (location.clone(), value)
+                    })
+                    .collect();
+
+                (
+                    compute_deltas(global_model, &filled_point_seqs)?,
+                    global_model.default.clone(),
+                )
+            }
+        };
 
         // Compute the default on the unrounded deltas
         let default_value = deltas
             .iter()
             .filter_map(|(region, value)| {
-                let scaler = region.scalar_at(&var_model.default).into_inner();
+                let scaler = region.scalar_at(&default_location).into_inner();
                 (scaler != 0.0).then_some(*value * scaler)
             })
             .sum::<f64>()
@@ -217,6 +600,29 @@ pub struct Message {
     pub span: Span,
 }
 
+/// One axis range in a `conditionset`, resolved to normalized (-1..1) F2Dot14
+/// coordinates as written into the GSUB `FeatureVariations` table.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct AxisCondition {
+    #[wasm_bindgen(js_name = "axisTag")]
+    pub axis_tag: String,
+    #[wasm_bindgen(js_name = "minValue")]
+    pub min_value: f64,
+    #[wasm_bindgen(js_name = "maxValue")]
+    pub max_value: f64,
+}
+
+/// The design-space region in which a `variation <tag> <conditionset> { ... };`
+/// block's substitution is active.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct FeatureVariationRange {
+    #[wasm_bindgen(js_name = "featureTag")]
+    pub feature_tag: String,
+    pub conditions: Vec<AxisCondition>,
+}
+
 #[wasm_bindgen(getter_with_clone)]
 #[derive(Default)]
 pub struct CompilationResult {
@@ -224,6 +630,8 @@ pub struct CompilationResult {
     pub font_data: Option<Vec<u8>>,
     #[wasm_bindgen(js_name = "insertMarkers")]
     pub insert_markers: Option<Vec<InsertMarker>>,
+    #[wasm_bindgen(js_name = "featureVariationRanges")]
+    pub feature_variation_ranges: Option<Vec<FeatureVariationRange>>,
     pub messages: Vec<Message>,
 }
 
@@ -254,6 +662,50 @@ fn to_utf16_offset(s: &str, byte_offset: usize) -> usize {
         .unwrap_or(byte_offset)
 }
 
+/// Resolves one GSUB `FeatureVariationRecord` to the feature tag it
+/// substitutes and the normalized axis ranges that activate it.
+fn feature_variation_range(
+    record: &FeatureVariationRecord,
+    gsub: &Gsub,
+    stat_axis_records: &[AxisRecord],
+) -> FeatureVariationRange {
+    let conditions = record
+        .condition_set
+        .as_ref()
+        .map(|condition_set| {
+            condition_set
+                .conditions
+                .iter()
+                .map(|condition| AxisCondition {
+                    axis_tag: stat_axis_records
+                        .get(condition.axis_index as usize)
+                        .map(|a| a.axis_tag.to_string())
+                        .unwrap_or_default(),
+                    min_value: f64::from(condition.filter_range_min_value),
+                    max_value: f64::from(condition.filter_range_max_value),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let feature_tag = record
+        .feature_table_substitution
+        .as_ref()
+        .and_then(|substitution| substitution.substitutions.first())
+        .and_then(|substitution| {
+            gsub.feature_list
+                .feature_records
+                .get(substitution.feature_index as usize)
+        })
+        .map(|record| record.feature_tag.to_string())
+        .unwrap_or_default();
+
+    FeatureVariationRange {
+        feature_tag,
+        conditions,
+    }
+}
+
 fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -271,6 +723,55 @@ pub fn build_shaper_font(
     #[wasm_bindgen(js_name = "glyphOrder")] glyph_order: Vec<String>,
     #[wasm_bindgen(js_name = "featureSource")] feature_source: String,
     axes: Option<Vec<AxisInfo>>,
+    instances: Option<Vec<InstanceInfo>>,
+    #[wasm_bindgen(js_name = "globalMasters")] global_masters: Option<Vec<MasterLocation>>,
+) -> Result<CompilationResult, JsError> {
+    compile(
+        units_per_em,
+        glyph_order,
+        feature_source,
+        axes,
+        instances,
+        global_masters,
+    )
+}
+
+/// Same as [`build_shaper_font`], but axes, their `avar` mappings, named
+/// instances, and the full set of master locations are parsed from a
+/// designspace XML document rather than hand-built by the caller.
+#[wasm_bindgen(js_name = buildShaperFontFromDesignspace)]
+pub fn build_shaper_font_from_designspace(
+    #[wasm_bindgen(js_name = "unitsPerEm")] units_per_em: u16,
+    #[wasm_bindgen(js_name = "glyphOrder")] glyph_order: Vec<String>,
+    #[wasm_bindgen(js_name = "featureSource")] feature_source: String,
+    #[wasm_bindgen(js_name = "designspaceSource")] designspace_source: String,
+) -> Result<CompilationResult, JsError> {
+    let parsed = match designspace::parse(&designspace_source) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            let mut res = CompilationResult::default();
+            res.messages.push(message);
+            return Ok(res);
+        }
+    };
+
+    compile(
+        units_per_em,
+        glyph_order,
+        feature_source,
+        Some(parsed.axes),
+        Some(parsed.instances),
+        Some(parsed.masters),
+    )
+}
+
+fn compile(
+    units_per_em: u16,
+    glyph_order: Vec<String>,
+    feature_source: String,
+    axes: Option<Vec<AxisInfo>>,
+    instances: Option<Vec<InstanceInfo>>,
+    global_masters: Option<Vec<MasterLocation>>,
 ) -> Result<CompilationResult, JsError> {
     set_panic_hook();
 
@@ -301,7 +802,16 @@ pub fn build_shaper_font(
         return Ok(res);
     }
 
-    let variation_info = axes.map(SimpleVariationInfo::new);
+    let (variation_info, axis_segment_maps) = match axes
+        .map(|axes| SimpleVariationInfo::try_new(axes, global_masters))
+    {
+        Some(Ok((info, segment_maps))) => (Some(info), segment_maps),
+        Some(Err(message)) => {
+            res.messages.push(message);
+            return Ok(res);
+        }
+        None => (None, Vec::new()),
+    };
 
     let diagnostics = validate(&tree, &glyph_map, variation_info.as_ref());
     res.add_diagnostics(&diagnostics, &tree);
@@ -338,6 +848,9 @@ pub fn build_shaper_font(
             compilation.head = Some(head_table);
 
             let mut fvar_axes = Vec::new();
+            let mut stat_axis_records = Vec::new();
+            let mut stat_axis_values = Vec::new();
+            let mut instance_records = Vec::new();
             if let Some(variation_info) = variation_info {
                 let mut name_table = compilation.name.take().unwrap_or_default();
                 let mut name_id = name_table
@@ -350,7 +863,7 @@ pub fn build_shaper_font(
                     .checked_add(1)
                     .unwrap();
 
-                for axis in variation_info.axes.iter() {
+                for (axis_index, axis) in variation_info.axes.iter().enumerate() {
                     name_table.name_record.push(NameRecord::new(
                         3,
                         1,
@@ -368,21 +881,151 @@ pub fn build_shaper_font(
                         ..Default::default()
                     });
 
+                    stat_axis_records.push(AxisRecord {
+                        axis_tag: axis.tag,
+                        axis_name_id: name_id,
+                        axis_ordering: axis_index as u16,
+                    });
+
                     name_id = name_id.checked_add(1).unwrap();
                 }
 
+                for instance in instances.into_iter().flatten() {
+                    let instance_value = |axis_tag: &str| -> Option<f64> {
+                        instance
+                            .coordinates
+                            .iter()
+                            .find(|c| c.axis_tag == axis_tag)
+                            .map(|c| c.value)
+                    };
+
+                    let coordinates: Vec<Fixed> = variation_info
+                        .axes
+                        .iter()
+                        .map(|axis| {
+                            instance_value(axis.tag.to_string().as_str())
+                                .map(UserCoord::new)
+                                .unwrap_or(axis.default)
+                                .into()
+                        })
+                        .collect();
+
+                    name_table.name_record.push(NameRecord::new(
+                        3,
+                        1,
+                        0x0409,
+                        name_id,
+                        instance.name.clone().into(),
+                    ));
+                    let subfamily_name_id = name_id;
+                    name_id = name_id.checked_add(1).unwrap();
+
+                    let postscript_name_id = instance.postscript_name.as_ref().map(|name| {
+                        name_table.name_record.push(NameRecord::new(
+                            3,
+                            1,
+                            0x0409,
+                            name_id,
+                            name.clone().into(),
+                        ));
+                        let id = name_id;
+                        name_id = name_id.checked_add(1).unwrap();
+                        id
+                    });
+
+                    instance_records.push(InstanceRecord {
+                        subfamily_name_id,
+                        flags: 0,
+                        coordinates,
+                        post_script_name_id: postscript_name_id,
+                    });
+
+                    for style_attribute in instance.style_attributes.iter().flatten() {
+                        let Ok(axis_tag) = Tag::from_str(&style_attribute.axis_tag) else {
+                            continue;
+                        };
+                        let Some(axis_index) = variation_info
+                            .axes
+                            .iter()
+                            .position(|axis| axis.tag == axis_tag)
+                        else {
+                            continue;
+                        };
+                        let nominal = instance_value(&style_attribute.axis_tag)
+                            .map(UserCoord::new)
+                            .unwrap_or(variation_info.axes.iter().nth(axis_index).unwrap().default);
+
+                        name_table.name_record.push(NameRecord::new(
+                            3,
+                            1,
+                            0x0409,
+                            name_id,
+                            style_attribute.label.clone().into(),
+                        ));
+                        let value_name_id = name_id;
+                        name_id = name_id.checked_add(1).unwrap();
+
+                        let axis_value = match (
+                            style_attribute.range_min_value,
+                            style_attribute.range_max_value,
+                        ) {
+                            (None, None) => AxisValue::Format1(AxisValueFormat1 {
+                                axis_index: axis_index as u16,
+                                value_name_id,
+                                value: nominal.into(),
+                                ..Default::default()
+                            }),
+                            (range_min, range_max) => AxisValue::Format2(AxisValueFormat2 {
+                                axis_index: axis_index as u16,
+                                value_name_id,
+                                nominal_value: nominal.into(),
+                                range_min_value: range_min.map(UserCoord::new).unwrap_or(nominal).into(),
+                                range_max_value: range_max.map(UserCoord::new).unwrap_or(nominal).into(),
+                                ..Default::default()
+                            }),
+                        };
+                        stat_axis_values.push(axis_value);
+                    }
+                }
+
                 compilation.name = Some(name_table);
             }
 
+            let feature_variation_ranges = compilation.gsub.as_ref().and_then(|gsub| {
+                gsub.feature_variations.as_ref().map(|feature_variations| {
+                    feature_variations
+                        .feature_variation_records
+                        .iter()
+                        .map(|record| feature_variation_range(record, gsub, &stat_axis_records))
+                        .collect()
+                })
+            });
+
             let mut builder = compilation.to_font_builder()?;
 
             if !fvar_axes.is_empty() {
-                let fvar_table = Fvar::new(AxisInstanceArrays::new(fvar_axes, Vec::new()));
+                let fvar_table = Fvar::new(AxisInstanceArrays::new(fvar_axes, instance_records));
                 builder.add_table(&fvar_table)?;
             }
 
+            if !stat_axis_records.is_empty() {
+                // Name ID 2 is the reserved "Regular" subfamily name, the
+                // conventional elided fallback for a STAT table.
+                let stat_table = Stat::new(stat_axis_records, stat_axis_values, NameId::from(2u16));
+                builder.add_table(&stat_table)?;
+            }
+
+            if axis_segment_maps.iter().any(Option::is_some) {
+                let axis_segment_maps = axis_segment_maps
+                    .into_iter()
+                    .map(|m| m.unwrap_or_else(|| SegmentMaps::new(Vec::new())))
+                    .collect();
+                builder.add_table(&Avar::new(axis_segment_maps))?;
+            }
+
             res.font_data = Some(builder.build());
             res.insert_markers = Some(insert_markers);
+            res.feature_variation_ranges = feature_variation_ranges;
             Ok(res)
         }
         Err(errors) => {
@@ -392,3 +1035,242 @@ pub fn build_shaper_font(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wght_axis() -> AxisInfo {
+        AxisInfo::new("wght".to_string(), 100.0, 400.0, 900.0, None)
+    }
+
+    fn normalized(value: f64) -> NormalizedLocation {
+        let mut location = NormalizedLocation::new();
+        location.insert(Tag::from_str("wght").unwrap(), NormalizedCoord::new(value));
+        location
+    }
+
+    // A metric defined at every global master shares the global model's
+    // region decomposition by construction. A metric missing one of those
+    // masters used to get its deltas from a model scoped to just its own
+    // (sparser) locations instead, which splits the design space into a
+    // different set of regions than every other metric uses.
+    #[test]
+    fn sparse_metric_shares_dense_metric_region_decomposition() {
+        let global_masters = Some(vec![
+            MasterLocation::new(vec![AxisCoordinate::new("wght".to_string(), 100.0)]),
+            MasterLocation::new(vec![AxisCoordinate::new("wght".to_string(), 400.0)]),
+            MasterLocation::new(vec![AxisCoordinate::new("wght".to_string(), 900.0)]),
+        ]);
+        let (info, _) = SimpleVariationInfo::try_new(vec![wght_axis()], global_masters).unwrap();
+
+        let dense_values = HashMap::from([
+            (normalized(-1.0), 0),
+            (normalized(0.0), 100),
+            (normalized(1.0), 300),
+        ]);
+        let sparse_values = HashMap::from([(normalized(0.0), 100), (normalized(1.0), 300)]);
+
+        let (dense_default, dense_deltas) = info.resolve_variable_metric(&dense_values).unwrap();
+        let (sparse_default, sparse_deltas) = info.resolve_variable_metric(&sparse_values).unwrap();
+
+        assert_eq!(dense_default, 100);
+        assert_eq!(sparse_default, 100);
+        assert!(!dense_deltas.is_empty());
+        assert_eq!(
+            dense_deltas.len(),
+            sparse_deltas.len(),
+            "a metric missing a global master should still decompose into the same number \
+             of regions as one defined everywhere, once it's resolved against the shared model"
+        );
+    }
+
+    // End-to-end: a `conditionset`/`variation` block in the FEA source should
+    // turn into a `FeatureVariationRecord`, which `feature_variation_range`
+    // resolves back to the feature tag and normalized axis range the caller
+    // can show in the UI.
+    #[test]
+    fn conditionset_variation_produces_feature_variation_range() {
+        let glyph_order = vec!["A".to_string(), "V".to_string()];
+        let feature_source = "
+languagesystem DFLT dflt;
+
+conditionset cond_bold {
+    wght 700 900;
+} cond_bold;
+
+feature rlig {
+    sub A by V;
+} rlig;
+
+variation rlig cond_bold {
+    sub A by A;
+} rlig;
+";
+
+        let result = compile(
+            1000,
+            glyph_order,
+            feature_source.to_string(),
+            Some(vec![wght_axis()]),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(
+            result.messages.iter().all(|m| m.level != "error"),
+            "unexpected errors: {:?}",
+            result.messages.iter().map(|m| &m.text).collect::<Vec<_>>()
+        );
+
+        let ranges = result
+            .feature_variation_ranges
+            .expect("a conditionset/variation block should produce a feature variation range");
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].feature_tag, "rlig");
+        assert_eq!(ranges[0].conditions.len(), 1);
+        assert_eq!(ranges[0].conditions[0].axis_tag, "wght");
+        assert!((ranges[0].conditions[0].min_value - 0.6).abs() < 0.001);
+        assert!((ranges[0].conditions[0].max_value - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn linear_normalize_maps_min_default_max_to_minus_one_zero_one() {
+        assert_eq!(linear_normalize(100.0, 400.0, 900.0, 100.0), -1.0);
+        assert_eq!(linear_normalize(100.0, 400.0, 900.0, 400.0), 0.0);
+        assert_eq!(linear_normalize(100.0, 400.0, 900.0, 900.0), 1.0);
+        assert_eq!(linear_normalize(100.0, 400.0, 900.0, 250.0), -0.5);
+        assert_eq!(linear_normalize(100.0, 400.0, 900.0, 650.0), 0.5);
+    }
+
+    #[test]
+    fn anchor_mapping_adds_missing_endpoints() {
+        let anchored = anchor_mapping(100.0, 400.0, 900.0, &[(400.0, 350.0)]);
+        assert_eq!(anchored, vec![(100.0, 100.0), (400.0, 350.0), (900.0, 900.0)]);
+    }
+
+    #[test]
+    fn build_segment_map_is_none_without_a_mapping() {
+        assert!(build_segment_map(100.0, 400.0, 900.0, &[]).is_none());
+    }
+
+    #[test]
+    fn build_segment_map_normalizes_each_anchor() {
+        let segment_map = build_segment_map(100.0, 400.0, 900.0, &[(400.0, 350.0)]).unwrap();
+        // The designer's (400, 350) entry stands in for the default anchor, so
+        // it plus the min/max anchors, each side normalized independently
+        // through min/default/max.
+        assert_eq!(segment_map.axis_value_maps.len(), 3);
+    }
+
+    #[test]
+    fn try_new_rejects_non_finite_mapping_values() {
+        let axis = AxisInfo::new(
+            "wght".to_string(),
+            100.0,
+            400.0,
+            900.0,
+            Some(vec![AxisMapEntry::new(400.0, f64::NAN)]),
+        );
+        let err = SimpleVariationInfo::try_new(vec![axis], None).unwrap_err();
+        assert!(err.text.contains("finite"));
+    }
+
+    #[test]
+    fn try_new_rejects_conflicting_duplicate_mapping_entries() {
+        let axis = AxisInfo::new(
+            "wght".to_string(),
+            100.0,
+            400.0,
+            900.0,
+            Some(vec![
+                AxisMapEntry::new(700.0, 650.0),
+                AxisMapEntry::new(700.0, 680.0),
+            ]),
+        );
+        let err = SimpleVariationInfo::try_new(vec![axis], None).unwrap_err();
+        assert!(err.text.contains("conflicting"));
+    }
+
+    #[test]
+    fn try_new_accepts_identical_duplicate_mapping_entries() {
+        let axis = AxisInfo::new(
+            "wght".to_string(),
+            100.0,
+            400.0,
+            900.0,
+            Some(vec![
+                AxisMapEntry::new(700.0, 650.0),
+                AxisMapEntry::new(700.0, 650.0),
+            ]),
+        );
+        assert!(SimpleVariationInfo::try_new(vec![axis], None).is_ok());
+    }
+
+    // End-to-end: a named instance with a STAT-labeling style attribute
+    // should compile without error and produce a font, exercising the
+    // `fvar`/`STAT`/`name` assembly together rather than any one in isolation.
+    #[test]
+    fn instance_with_style_attribute_compiles() {
+        let glyph_order = vec!["A".to_string()];
+        let feature_source = "languagesystem DFLT dflt;\n";
+
+        let instance = InstanceInfo::new(
+            "Bold".to_string(),
+            Some("MyFont-Bold".to_string()),
+            vec![AxisCoordinate::new("wght".to_string(), 700.0)],
+            Some(vec![StyleAttribute::new(
+                "wght".to_string(),
+                "Bold".to_string(),
+                None,
+                None,
+            )]),
+        );
+
+        let result = compile(
+            1000,
+            glyph_order,
+            feature_source.to_string(),
+            Some(vec![wght_axis()]),
+            Some(vec![instance]),
+            None,
+        )
+        .unwrap();
+
+        assert!(
+            result.messages.iter().all(|m| m.level != "error"),
+            "unexpected errors: {:?}",
+            result.messages.iter().map(|m| &m.text).collect::<Vec<_>>()
+        );
+        assert!(result.font_data.is_some());
+    }
+
+    // A designspace `<source>`/`<instance>` location's `xvalue` is a
+    // design-space coordinate, i.e. already on the far side of the axis's
+    // `<map>`. At a non-anchor point that's a different number than the
+    // user-space value `linear_normalize` expects, so it has to be inverted
+    // through the mapping first.
+    #[test]
+    fn design_to_user_inverts_a_nonlinear_map_at_a_non_anchor_point() {
+        let anchored = anchor_mapping(100.0, 400.0, 900.0, &[(400.0, 300.0)]);
+
+        // 300 is the *design-space* value the designer's map pins to the
+        // user-space default (400). Read naively as a user-space value (the
+        // pre-fix behavior) it would normalize to something other than 0.
+        let user = design_to_user(&anchored, 300.0);
+        assert_eq!(user, 400.0);
+        assert_eq!(linear_normalize(100.0, 400.0, 900.0, user), 0.0);
+
+        // A genuinely non-anchor design-space point, between the
+        // (400 -> 300) and (900 -> 900) anchors.
+        let user = design_to_user(&anchored, 650.0);
+        assert!((user - 691.666_666_7).abs() < 0.001);
+        assert_ne!(user, 650.0);
+    }
+
+    #[test]
+    fn design_to_user_is_identity_without_a_mapping() {
+        assert_eq!(design_to_user(&[], 650.0), 650.0);
+    }
+}
@@ -38,6 +38,8 @@ feature aalt {
         glyph_order,
         feature_source.to_string(),
         JsValue::NULL,
+        JsValue::NULL,
+        JsValue::NULL,
     );
     assert!(result.is_ok());
     let result = result.unwrap();